@@ -8,35 +8,49 @@
 
 use super::super::arch::choose::{
     syscall0_readonly, syscall1, syscall1_noreturn, syscall1_readonly, syscall2, syscall2_readonly,
-    syscall3, syscall3_readonly, syscall4,
+    syscall3, syscall3_readonly, syscall4, syscall5,
 };
 use super::super::c;
 use super::super::conv::{
-    borrowed_fd, by_mut, c_int, c_str, c_uint, out, ret, ret_c_int, ret_c_uint, ret_infallible,
-    ret_usize, ret_usize_infallible, size_of, slice_just_addr, slice_mut, void_star, zero,
+    borrowed_fd, by_mut, by_ref, c_int, c_str, c_uint, out, ret, ret_c_int, ret_c_uint,
+    ret_infallible, ret_owned_fd, ret_usize, ret_usize_infallible, size_of, slice_just_addr,
+    slice_mut, void_star, zero,
 };
 use super::super::reg::nr;
 use super::{RawCpuSet, RawUname};
-use crate::fd::BorrowedFd;
+use crate::fd::{AsRawFd, BorrowedFd, OwnedFd};
 use crate::ffi::ZStr;
 use crate::io;
 use crate::process::{
-    Cpuid, Gid, MembarrierCommand, MembarrierQuery, Pid, RawNonZeroPid, RawPid, Resource, Rlimit,
-    Uid, WaitOptions, WaitStatus,
+    Cpuid, Gid, MembarrierCommand, MembarrierQuery, Pid, PidfdFlags, RawNonZeroPid, RawPid,
+    Resource, Rlimit, Siginfo, Signal, Uid, WaitId, WaitIdOptions, WaitIdStatus, WaitOptions,
+    WaitStatus,
 };
 use core::mem::MaybeUninit;
 use linux_raw_sys::general::{
     __NR_chdir, __NR_exit_group, __NR_fchdir, __NR_getcwd, __NR_getpid, __NR_getppid,
     __NR_getpriority, __NR_sched_getaffinity, __NR_sched_setaffinity, __NR_sched_yield,
-    __NR_setpriority, __NR_uname, __NR_wait4, __kernel_gid_t, __kernel_pid_t, __kernel_uid_t,
+    __NR_setpriority, __NR_uname, __NR_wait4, __NR_waitid, __kernel_gid_t, __kernel_pid_t,
+    __kernel_uid_t, P_ALL, P_PGID, P_PID, P_PIDFD,
 };
 #[cfg(not(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm")))]
-use linux_raw_sys::general::{__NR_getegid, __NR_geteuid, __NR_getgid, __NR_getuid};
+use linux_raw_sys::general::{
+    __NR_getegid, __NR_geteuid, __NR_getgid, __NR_getresgid, __NR_getresuid, __NR_getuid,
+    __NR_setgid, __NR_setresgid, __NR_setresuid, __NR_setuid,
+};
 #[cfg(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm"))]
-use linux_raw_sys::general::{__NR_getegid32, __NR_geteuid32, __NR_getgid32, __NR_getuid32};
-use linux_raw_sys::v5_4::general::{__NR_membarrier, __NR_prlimit64};
+use linux_raw_sys::general::{
+    __NR_getegid32, __NR_geteuid32, __NR_getgid32, __NR_getresgid32, __NR_getresuid32,
+    __NR_getuid32, __NR_setgid32, __NR_setresgid32, __NR_setresuid32, __NR_setuid32,
+};
+use linux_raw_sys::v5_4::general::{
+    __NR_membarrier, __NR_pidfd_open, __NR_pidfd_send_signal, __NR_prlimit64,
+};
 #[cfg(target_pointer_width = "32")]
-use {core::convert::TryInto, linux_raw_sys::general::__NR_getrlimit};
+use {
+    core::convert::TryInto,
+    linux_raw_sys::general::{__NR_getrlimit, __NR_setrlimit},
+};
 
 #[inline]
 pub(crate) fn chdir(filename: &ZStr) -> io::Result<()> {
@@ -175,6 +189,156 @@ pub(crate) fn geteuid() -> Uid {
     }
 }
 
+#[inline]
+pub(crate) fn getresuid() -> (Uid, Uid, Uid) {
+    #[cfg(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm"))]
+    unsafe {
+        // `getresuid32` writes full 32-bit `uid_t` values through these
+        // pointers, so the output slots must be 32-bit even though
+        // `__kernel_uid_t` is 16-bit on these architectures.
+        let mut ruid = MaybeUninit::<u32>::uninit();
+        let mut euid = MaybeUninit::<u32>::uninit();
+        let mut suid = MaybeUninit::<u32>::uninit();
+        ret_infallible(syscall3(
+            nr(__NR_getresuid32),
+            out(&mut ruid),
+            out(&mut euid),
+            out(&mut suid),
+        ));
+        (
+            Uid::from_raw(ruid.assume_init()),
+            Uid::from_raw(euid.assume_init()),
+            Uid::from_raw(suid.assume_init()),
+        )
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm")))]
+    unsafe {
+        let mut ruid = MaybeUninit::<__kernel_uid_t>::uninit();
+        let mut euid = MaybeUninit::<__kernel_uid_t>::uninit();
+        let mut suid = MaybeUninit::<__kernel_uid_t>::uninit();
+        ret_infallible(syscall3(
+            nr(__NR_getresuid),
+            out(&mut ruid),
+            out(&mut euid),
+            out(&mut suid),
+        ));
+        (
+            Uid::from_raw(ruid.assume_init()),
+            Uid::from_raw(euid.assume_init()),
+            Uid::from_raw(suid.assume_init()),
+        )
+    }
+}
+
+#[inline]
+pub(crate) fn getresgid() -> (Gid, Gid, Gid) {
+    #[cfg(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm"))]
+    unsafe {
+        // `getresgid32` writes full 32-bit `gid_t` values through these
+        // pointers, so the output slots must be 32-bit even though
+        // `__kernel_gid_t` is 16-bit on these architectures.
+        let mut rgid = MaybeUninit::<u32>::uninit();
+        let mut egid = MaybeUninit::<u32>::uninit();
+        let mut sgid = MaybeUninit::<u32>::uninit();
+        ret_infallible(syscall3(
+            nr(__NR_getresgid32),
+            out(&mut rgid),
+            out(&mut egid),
+            out(&mut sgid),
+        ));
+        (
+            Gid::from_raw(rgid.assume_init()),
+            Gid::from_raw(egid.assume_init()),
+            Gid::from_raw(sgid.assume_init()),
+        )
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm")))]
+    unsafe {
+        let mut rgid = MaybeUninit::<__kernel_gid_t>::uninit();
+        let mut egid = MaybeUninit::<__kernel_gid_t>::uninit();
+        let mut sgid = MaybeUninit::<__kernel_gid_t>::uninit();
+        ret_infallible(syscall3(
+            nr(__NR_getresgid),
+            out(&mut rgid),
+            out(&mut egid),
+            out(&mut sgid),
+        ));
+        (
+            Gid::from_raw(rgid.assume_init()),
+            Gid::from_raw(egid.assume_init()),
+            Gid::from_raw(sgid.assume_init()),
+        )
+    }
+}
+
+#[inline]
+pub(crate) fn setresuid(ruid: Uid, euid: Uid, suid: Uid) -> io::Result<()> {
+    #[cfg(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm"))]
+    unsafe {
+        ret(syscall3_readonly(
+            nr(__NR_setresuid32),
+            c_uint(ruid.as_raw()),
+            c_uint(euid.as_raw()),
+            c_uint(suid.as_raw()),
+        ))
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm")))]
+    unsafe {
+        ret(syscall3_readonly(
+            nr(__NR_setresuid),
+            c_uint(ruid.as_raw()),
+            c_uint(euid.as_raw()),
+            c_uint(suid.as_raw()),
+        ))
+    }
+}
+
+#[inline]
+pub(crate) fn setresgid(rgid: Gid, egid: Gid, sgid: Gid) -> io::Result<()> {
+    #[cfg(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm"))]
+    unsafe {
+        ret(syscall3_readonly(
+            nr(__NR_setresgid32),
+            c_uint(rgid.as_raw()),
+            c_uint(egid.as_raw()),
+            c_uint(sgid.as_raw()),
+        ))
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm")))]
+    unsafe {
+        ret(syscall3_readonly(
+            nr(__NR_setresgid),
+            c_uint(rgid.as_raw()),
+            c_uint(egid.as_raw()),
+            c_uint(sgid.as_raw()),
+        ))
+    }
+}
+
+#[inline]
+pub(crate) fn setuid(uid: Uid) -> io::Result<()> {
+    #[cfg(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm"))]
+    unsafe {
+        ret(syscall1_readonly(nr(__NR_setuid32), c_uint(uid.as_raw())))
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm")))]
+    unsafe {
+        ret(syscall1_readonly(nr(__NR_setuid), c_uint(uid.as_raw())))
+    }
+}
+
+#[inline]
+pub(crate) fn setgid(gid: Gid) -> io::Result<()> {
+    #[cfg(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm"))]
+    unsafe {
+        ret(syscall1_readonly(nr(__NR_setgid32), c_uint(gid.as_raw())))
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "sparc", target_arch = "arm")))]
+    unsafe {
+        ret(syscall1_readonly(nr(__NR_setgid), c_uint(gid.as_raw())))
+    }
+}
+
 #[inline]
 pub(crate) fn sched_getaffinity(pid: Option<Pid>, cpuset: &mut RawCpuSet) -> io::Result<()> {
     unsafe {
@@ -385,6 +549,106 @@ pub(crate) fn getrlimit(limit: Resource) -> Rlimit {
     }
 }
 
+/// Convert a kernel `rlimit64` into a `Rlimit`, mapping `RLIM64_INFINITY`
+/// back to `None`.
+#[inline]
+fn rlimit_from_rlimit64(lim: linux_raw_sys::v5_4::general::rlimit64) -> Rlimit {
+    let current = if lim.rlim_cur == linux_raw_sys::v5_4::general::RLIM64_INFINITY as _ {
+        None
+    } else {
+        Some(lim.rlim_cur)
+    };
+    let maximum = if lim.rlim_max == linux_raw_sys::v5_4::general::RLIM64_INFINITY as _ {
+        None
+    } else {
+        Some(lim.rlim_max)
+    };
+    Rlimit { current, maximum }
+}
+
+/// Convert a `Rlimit` into a kernel `rlimit64`, mapping `None` fields onto
+/// `RLIM64_INFINITY`.
+#[inline]
+fn rlimit_to_rlimit64(lim: &Rlimit) -> linux_raw_sys::v5_4::general::rlimit64 {
+    linux_raw_sys::v5_4::general::rlimit64 {
+        rlim_cur: lim
+            .current
+            .unwrap_or(linux_raw_sys::v5_4::general::RLIM64_INFINITY as _),
+        rlim_max: lim
+            .maximum
+            .unwrap_or(linux_raw_sys::v5_4::general::RLIM64_INFINITY as _),
+    }
+}
+
+#[inline]
+pub(crate) fn setrlimit(limit: Resource, new: Rlimit) -> io::Result<()> {
+    let lim = rlimit_to_rlimit64(&new);
+    #[cfg(target_pointer_width = "32")]
+    unsafe {
+        match ret(syscall4(
+            nr(__NR_prlimit64),
+            c_uint(0),
+            c_uint(limit as c::c_uint),
+            by_ref(&lim),
+            void_star(core::ptr::null_mut()),
+        )) {
+            Ok(()) => Ok(()),
+            Err(e) if e == io::Error::NOSYS => {
+                let convert = |limit: Option<u64>| match limit {
+                    // A finite limit that doesn't fit the 32-bit `rlimit` must
+                    // not be silently turned into "unlimited".
+                    Some(value) => value.try_into().map_err(|_| io::Error::INVAL),
+                    None => Ok(linux_raw_sys::general::RLIM_INFINITY as _),
+                };
+                let old = linux_raw_sys::general::rlimit {
+                    rlim_cur: convert(new.current)?,
+                    rlim_max: convert(new.maximum)?,
+                };
+                ret(syscall2(
+                    nr(__NR_setrlimit),
+                    c_uint(limit as c::c_uint),
+                    by_ref(&old),
+                ))
+            }
+            Err(e) => Err(e),
+        }
+    }
+    #[cfg(target_pointer_width = "64")]
+    unsafe {
+        ret(syscall4(
+            nr(__NR_prlimit64),
+            c_uint(0),
+            c_uint(limit as c::c_uint),
+            by_ref(&lim),
+            void_star(core::ptr::null_mut()),
+        ))
+    }
+}
+
+#[inline]
+pub(crate) fn prlimit(
+    pid: Option<Pid>,
+    limit: Resource,
+    new: Option<Rlimit>,
+) -> io::Result<Rlimit> {
+    let lim = new.map(|new| rlimit_to_rlimit64(&new));
+    let new_ptr = match &lim {
+        Some(lim) => by_ref(lim),
+        None => void_star(core::ptr::null_mut()),
+    };
+    let mut result = MaybeUninit::<linux_raw_sys::v5_4::general::rlimit64>::uninit();
+    unsafe {
+        ret(syscall4(
+            nr(__NR_prlimit64),
+            c_uint(Pid::as_raw(pid)),
+            c_uint(limit as c::c_uint),
+            new_ptr,
+            out(&mut result),
+        ))?;
+        Ok(rlimit_from_rlimit64(result.assume_init()))
+    }
+}
+
 #[inline]
 pub(crate) fn wait(waitopts: WaitOptions) -> io::Result<Option<(Pid, WaitStatus)>> {
     _waitpid(!0, waitopts)
@@ -417,6 +681,86 @@ pub(crate) fn _waitpid(
     }
 }
 
+#[inline]
+pub(crate) fn waitid(id: WaitId<'_>, options: WaitIdOptions) -> io::Result<Option<WaitIdStatus>> {
+    // `waitid` can return successfully without having filled in `status`, if
+    // `WNOHANG`/`WNOWAIT` was passed and no child was ready. Zero it up front
+    // so that we can recognize that case by inspecting `si_pid` below.
+    let mut status = MaybeUninit::<c::siginfo_t>::zeroed();
+    let (idtype, idn) = match id {
+        WaitId::All => (P_ALL, 0),
+        WaitId::Pid(pid) => (P_PID, Pid::as_raw(Some(pid))),
+        WaitId::Pgid(pgid) => (P_PGID, Pid::as_raw(pgid)),
+        WaitId::PidFd(fd) => (P_PIDFD, fd.as_raw_fd() as _),
+    };
+    unsafe {
+        ret(syscall5(
+            nr(__NR_waitid),
+            c_uint(idtype),
+            c_uint(idn),
+            out(&mut status),
+            c_int(options.bits() as _),
+            zero(),
+        ))?;
+    }
+    Ok(unsafe { cvt_waitid_status(status) })
+}
+
+/// Convert the `siginfo_t` filled in by `waitid` into a `WaitIdStatus`.
+///
+/// Returns `None` when no child was waited for, which the caller requests by
+/// passing `WNOHANG` or `WNOWAIT`; this is signalled by a zero `si_pid`.
+///
+/// # Safety
+///
+/// `status` must have been zeroed and then passed to a `waitid` call which
+/// returned successfully.
+#[inline]
+unsafe fn cvt_waitid_status(status: MaybeUninit<c::siginfo_t>) -> Option<WaitIdStatus> {
+    let status = status.assume_init();
+    // `si_pid` aliases other fields in the `siginfo_t` union, but for a
+    // `SIGCHLD`-shaped result it holds the pid of the child, and a zero there
+    // means `waitid` didn't reap anything.
+    if status.__bindgen_anon_1.__bindgen_anon_1._sifields._sigchld._pid == 0 {
+        None
+    } else {
+        Some(WaitIdStatus(status))
+    }
+}
+
+#[inline]
+pub(crate) fn pidfd_open(pid: Pid, flags: PidfdFlags) -> io::Result<OwnedFd> {
+    unsafe {
+        ret_owned_fd(syscall2_readonly(
+            nr(__NR_pidfd_open),
+            c_uint(Pid::as_raw(Some(pid))),
+            c_uint(flags.bits()),
+        ))
+    }
+}
+
+#[inline]
+pub(crate) fn pidfd_send_signal(
+    pidfd: BorrowedFd<'_>,
+    sig: Signal,
+    info: Option<&Siginfo>,
+    flags: c::c_uint,
+) -> io::Result<()> {
+    let info_ptr = match info {
+        Some(info) => by_ref(info),
+        None => void_star(core::ptr::null_mut()),
+    };
+    unsafe {
+        ret(syscall4(
+            nr(__NR_pidfd_send_signal),
+            borrowed_fd(pidfd),
+            c_int(sig as c::c_int),
+            info_ptr,
+            c_uint(flags),
+        ))
+    }
+}
+
 #[inline]
 pub(crate) fn exit_group(code: c::c_int) -> ! {
     unsafe { syscall1_noreturn(nr(__NR_exit_group), c_int(code)) }